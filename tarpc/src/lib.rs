@@ -0,0 +1,24 @@
+// Copyright 2018 Google LLC
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! An RPC framework for Rust with a focus on ease of use.
+//!
+//! This crate currently only contains the `transport` layer -- the `Transport` trait, its wire
+//! formats, and the TCP/TLS/QUIC/WebSocket integrations built on it. The generated-service
+//! machinery (`context`, `server`, and the `service!` macro that `example-service` is written
+//! against) lives outside this snapshot, so `example-service` can't build against this crate
+//! alone.
+
+#![feature(
+    underscore_imports,
+    futures_api,
+    pin,
+    arbitrary_self_types,
+    await_macro,
+    async_await,
+)]
+
+pub mod transport;
@@ -0,0 +1,41 @@
+// Copyright 2018 Google LLC
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! A length-delimited, bincode-encoded transport.
+//!
+//! Unlike a line- or string-based codec, bincode can't resume parsing a partially-read value, so
+//! each message is framed behind a length prefix by [`transport::codec`](crate::transport::codec).
+
+use super::codec::{self, Format};
+use bytes::Bytes;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{io, net::SocketAddr};
+
+/// The bincode wire [`Format`].
+pub struct Bincode;
+
+impl Format for Bincode {
+    fn encode<SinkItem: Serialize>(item: &SinkItem) -> io::Result<Bytes> {
+        bincode::serialize(item).map(Bytes::from).map_err(codec::io_err)
+    }
+
+    fn decode<Item: DeserializeOwned>(frame: &[u8]) -> io::Result<Item> {
+        bincode::deserialize(frame).map_err(codec::io_err)
+    }
+}
+
+/// Returns a length-delimited bincode [`Transport`](crate::transport::Transport) over `io`.
+pub fn new<Item, SinkItem>(
+    io: impl tokio_io::AsyncRead + tokio_io::AsyncWrite,
+    local_addr: SocketAddr,
+    peer_addr: SocketAddr,
+) -> impl crate::transport::Transport<Item = Item, SinkItem = SinkItem>
+where
+    Item: DeserializeOwned,
+    SinkItem: Serialize,
+{
+    codec::new::<Bincode, _, _>(io, local_addr, peer_addr)
+}
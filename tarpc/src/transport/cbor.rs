@@ -0,0 +1,41 @@
+// Copyright 2018 Google LLC
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! A length-delimited, CBOR-encoded transport, using `ciborium` for the compact binary
+//! encoding.
+
+use super::codec::{self, Format};
+use bytes::Bytes;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{io, net::SocketAddr};
+
+/// The CBOR wire [`Format`].
+pub struct Cbor;
+
+impl Format for Cbor {
+    fn encode<SinkItem: Serialize>(item: &SinkItem) -> io::Result<Bytes> {
+        let mut buf = Vec::new();
+        ciborium::ser::into_writer(item, &mut buf).map_err(codec::io_err)?;
+        Ok(Bytes::from(buf))
+    }
+
+    fn decode<Item: DeserializeOwned>(frame: &[u8]) -> io::Result<Item> {
+        ciborium::de::from_reader(frame).map_err(codec::io_err)
+    }
+}
+
+/// Returns a length-delimited CBOR [`Transport`](crate::transport::Transport) over `io`.
+pub fn new<Item, SinkItem>(
+    io: impl tokio_io::AsyncRead + tokio_io::AsyncWrite,
+    local_addr: SocketAddr,
+    peer_addr: SocketAddr,
+) -> impl crate::transport::Transport<Item = Item, SinkItem = SinkItem>
+where
+    Item: DeserializeOwned,
+    SinkItem: Serialize,
+{
+    codec::new::<Cbor, _, _>(io, local_addr, peer_addr)
+}
@@ -0,0 +1,38 @@
+// Copyright 2018 Google LLC
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! A length-delimited, JSON-encoded transport.
+
+use super::codec::{self, Format};
+use bytes::Bytes;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{io, net::SocketAddr};
+
+/// The JSON wire [`Format`].
+pub struct Json;
+
+impl Format for Json {
+    fn encode<SinkItem: Serialize>(item: &SinkItem) -> io::Result<Bytes> {
+        serde_json::to_vec(item).map(Bytes::from).map_err(codec::io_err)
+    }
+
+    fn decode<Item: DeserializeOwned>(frame: &[u8]) -> io::Result<Item> {
+        serde_json::from_slice(frame).map_err(codec::io_err)
+    }
+}
+
+/// Returns a length-delimited JSON [`Transport`](crate::transport::Transport) over `io`.
+pub fn new<Item, SinkItem>(
+    io: impl tokio_io::AsyncRead + tokio_io::AsyncWrite,
+    local_addr: SocketAddr,
+    peer_addr: SocketAddr,
+) -> impl crate::transport::Transport<Item = Item, SinkItem = SinkItem>
+where
+    Item: DeserializeOwned,
+    SinkItem: Serialize,
+{
+    codec::new::<Json, _, _>(io, local_addr, peer_addr)
+}
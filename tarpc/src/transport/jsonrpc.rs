@@ -0,0 +1,226 @@
+// Copyright 2018 Google LLC
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! A JSON-RPC 2.0 wire-compatibility layer.
+//!
+//! This defines the [JSON-RPC 2.0](https://www.jsonrpc.org/specification) envelope -- [`Request`]
+//! and [`Response`] -- plus [`serve`], which reads and responds to envelopes over any
+//! `AsyncRead + AsyncWrite`. Each generated RPC method name maps to the envelope's `method`; the
+//! generated arguments become `params`, either positional or named; and `context::Context`'s
+//! request id becomes the envelope's `id`. A request with no `id` is a notification and gets no
+//! response.
+//!
+//! `service!`-generated code is expected to implement [`Dispatch`] to route an incoming
+//! [`Request`] to the right method and turn its return value into a [`Response`]. That
+//! generated `impl Dispatch` would live alongside the `service!` macro, `context`, and the
+//! generated-service core -- none of which are part of this repository snapshot -- so `serve`
+//! has no real `Dispatch` impl to call it with here, and this layer can't be wired up to
+//! `HelloServer`-style services until those land. [`call`] doesn't depend on `Dispatch` at all
+//! and works today: it sends one request and awaits its response over any JSON-RPC 2.0 peer.
+//!
+//! Framing is newline-delimited JSON, the same as most JSON-RPC servers in the wild, rather than
+//! the length-prefixed binary framing used by `transport::bincode`.
+
+use super::codec;
+use futures::{future::BoxFuture, prelude::*};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io;
+use tokio::codec::LinesCodec;
+
+/// The `params` of a JSON-RPC request: either positional or named arguments.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Params {
+    /// Arguments in the generated method's declaration order.
+    Positional(Vec<Value>),
+    /// Arguments keyed by the generated method's parameter names.
+    Named(serde_json::Map<String, Value>),
+}
+
+/// A JSON-RPC 2.0 request envelope. `id` is `None` for a notification, which gets no [`Response`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Request {
+    jsonrpc: V2,
+    /// The generated RPC method's name, e.g. `"hello"` or `"add"`.
+    pub method: String,
+    /// The generated RPC method's arguments.
+    pub params: Params,
+    /// Carries `context::Context`'s request id; absent for a fire-and-forget notification.
+    pub id: Option<Value>,
+}
+
+/// A JSON-RPC 2.0 response envelope: exactly one of `result`/`error` is set, per the spec.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    jsonrpc: V2,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Error>,
+    pub id: Value,
+}
+
+/// A JSON-RPC 2.0 error object.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Error {
+    pub code: i64,
+    pub message: String,
+}
+
+impl Response {
+    /// Builds a success response carrying `result`, echoing the request's `id`.
+    pub fn ok(id: Value, result: Value) -> Self {
+        Response {
+            jsonrpc: V2,
+            result: Some(result),
+            error: None,
+            id,
+        }
+    }
+
+    /// Builds an error response, echoing the request's `id`.
+    pub fn err(id: Value, code: i64, message: impl Into<String>) -> Self {
+        Response {
+            jsonrpc: V2,
+            result: None,
+            error: Some(Error {
+                code,
+                message: message.into(),
+            }),
+            id,
+        }
+    }
+}
+
+/// Implemented by the code `service!` generates for a `Service`, mapping an incoming JSON-RPC
+/// [`Request`] to the matching generated method and its return value to a [`Response`].
+/// Notifications (`request.id.is_none()`) are dispatched but produce no response.
+pub trait Dispatch {
+    /// Routes `request` to the matching generated method, returning its JSON-RPC response, or
+    /// `None` if `request` was a notification.
+    fn dispatch(self, request: Request) -> BoxFuture<'static, Option<Response>>;
+}
+
+/// `serde` helper: (de)serializes the fixed `"jsonrpc": "2.0"` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct V2;
+
+impl Serialize for V2 {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str("2.0")
+    }
+}
+
+impl<'de> Deserialize<'de> for V2 {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        if s == "2.0" {
+            Ok(V2)
+        } else {
+            Err(serde::de::Error::custom(format!("unsupported jsonrpc version {}", s)))
+        }
+    }
+}
+
+/// Serves `dispatch` over `io`, reading newline-delimited [`Request`]s and writing back
+/// [`Response`]s, skipping notifications.
+pub async fn serve<D>(
+    io: impl tokio_io::AsyncRead + tokio_io::AsyncWrite,
+    dispatch: D,
+) -> io::Result<()>
+where
+    D: Dispatch + Clone,
+{
+    let mut transport = tokio::codec::Framed::new(io, LinesCodec::new());
+    while let Some(line) = await!(transport.next()) {
+        let line = line?;
+        let request: Request = serde_json::from_str(&line).map_err(codec::io_err)?;
+        if let Some(response) = await!(dispatch.clone().dispatch(request)) {
+            let body = serde_json::to_string(&response).map_err(codec::io_err)?;
+            await!(transport.send(body))?;
+        }
+    }
+    Ok(())
+}
+
+/// Sends one JSON-RPC `method` call carrying `params` over `io`, tagged with `id`, and returns the
+/// first [`Response`] read back from `io`. The response's `id` is not compared against `id`.
+pub async fn call(
+    io: impl tokio_io::AsyncRead + tokio_io::AsyncWrite,
+    method: impl Into<String>,
+    params: Params,
+    id: Value,
+) -> io::Result<Response> {
+    let mut transport = tokio::codec::Framed::new(io, LinesCodec::new());
+    let request = Request {
+        jsonrpc: V2,
+        method: method.into(),
+        params,
+        id: Some(id),
+    };
+    let body = serde_json::to_string(&request).map_err(codec::io_err)?;
+    await!(transport.send(body))?;
+
+    let line = await!(transport.next())
+        .ok_or_else(|| codec::io_err("connection closed before a response arrived"))??;
+    serde_json::from_str(&line).map_err(codec::io_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params_positional_round_trips() {
+        let params = Params::Positional(vec![Value::from("Bilbo"), Value::from("Baggins")]);
+        let json = serde_json::to_string(&params).unwrap();
+        assert_eq!(json, r#"["Bilbo","Baggins"]"#);
+        let decoded: Params = serde_json::from_str(&json).unwrap();
+        match decoded {
+            Params::Positional(args) => assert_eq!(args, vec![Value::from("Bilbo"), Value::from("Baggins")]),
+            Params::Named(_) => panic!("expected Positional"),
+        }
+    }
+
+    #[test]
+    fn params_named_round_trips() {
+        let mut map = serde_json::Map::new();
+        map.insert("first".to_owned(), Value::from("Bilbo"));
+        map.insert("last".to_owned(), Value::from("Baggins"));
+        let params = Params::Named(map.clone());
+        let json = serde_json::to_string(&params).unwrap();
+        let decoded: Params = serde_json::from_str(&json).unwrap();
+        match decoded {
+            Params::Named(decoded_map) => assert_eq!(decoded_map, map),
+            Params::Positional(_) => panic!("expected Named"),
+        }
+    }
+
+    #[test]
+    fn v2_accepts_only_the_literal_version_string() {
+        let v2: V2 = serde_json::from_str(r#""2.0""#).unwrap();
+        assert_eq!(v2, V2);
+        assert!(serde_json::from_str::<V2>(r#""1.0""#).is_err());
+    }
+
+    #[test]
+    fn response_ok_serializes_without_an_error_field() {
+        let response = Response::ok(Value::from(1), Value::from("Hello, Bilbo Baggins!"));
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(json, r#"{"jsonrpc":"2.0","result":"Hello, Bilbo Baggins!","id":1}"#);
+    }
+
+    #[test]
+    fn response_err_serializes_without_a_result_field() {
+        let response = Response::err(Value::from(1), -32601, "method not found");
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            json,
+            r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"method not found"},"id":1}"#
+        );
+    }
+}
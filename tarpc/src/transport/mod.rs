@@ -0,0 +1,123 @@
+// Copyright 2018 Google LLC
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! Transports carry the serialized requests and responses exchanged by a tarpc client and
+//! server. A [`Transport`] is a `Stream` of incoming messages paired with a `Sink` of outgoing
+//! ones, plus the local and peer addresses of the underlying connection. `tarpc::server::Server`
+//! and `tarpc::client` are generic over any `Transport` impl, so new wire formats and network
+//! protocols can be added without touching the RPC machinery itself.
+
+use futures::prelude::*;
+use std::{
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+pub mod codec;
+#[cfg(feature = "json")]
+pub mod jsonrpc;
+pub mod proxy_protocol;
+pub mod quic;
+pub mod tls;
+pub mod ws;
+
+#[cfg(feature = "bincode")]
+pub mod bincode;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(feature = "json")]
+pub mod json;
+
+/// A bidirectional channel of RPC messages, annotated with the addresses of the two endpoints.
+pub trait Transport:
+    Stream<Item = io::Result<<Self as Transport>::Item>>
+    + Sink<<Self as Transport>::SinkItem, SinkError = io::Error>
+{
+    /// The type of items received from the other end of this transport.
+    type Item;
+    /// The type of items sent to the other end of this transport.
+    type SinkItem;
+
+    /// Returns the address of this transport's local endpoint.
+    fn local_addr(&self) -> SocketAddr;
+    /// Returns the address of this transport's peer.
+    fn peer_addr(&self) -> SocketAddr;
+}
+
+/// Wraps `inner` -- a `Stream` + `Sink` of already-(de)serialized messages -- in a [`Transport`]
+/// that also knows the connection's local and peer addresses.
+pub fn new<T, Item, SinkItem>(
+    inner: T,
+    local_addr: SocketAddr,
+    peer_addr: SocketAddr,
+) -> impl Transport<Item = Item, SinkItem = SinkItem>
+where
+    T: Stream<Item = io::Result<Item>> + Sink<SinkItem, SinkError = io::Error> + Unpin,
+{
+    Wrap {
+        inner,
+        local_addr,
+        peer_addr,
+    }
+}
+
+struct Wrap<T> {
+    inner: T,
+    local_addr: SocketAddr,
+    peer_addr: SocketAddr,
+}
+
+impl<T, Item> Stream for Wrap<T>
+where
+    T: Stream<Item = io::Result<Item>> + Unpin,
+{
+    type Item = io::Result<Item>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
+
+impl<T, SinkItem> Sink<SinkItem> for Wrap<T>
+where
+    T: Sink<SinkItem, SinkError = io::Error> + Unpin,
+{
+    type SinkError = io::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::SinkError>> {
+        Pin::new(&mut self.inner).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: SinkItem) -> Result<(), Self::SinkError> {
+        Pin::new(&mut self.inner).start_send(item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::SinkError>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::SinkError>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+impl<T, Item, SinkItem> Transport for Wrap<T>
+where
+    T: Stream<Item = io::Result<Item>> + Sink<SinkItem, SinkError = io::Error> + Unpin,
+{
+    type Item = Item;
+    type SinkItem = SinkItem;
+
+    fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright 2018 Google LLC
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! A QUIC transport, built on `quinn`.
+//!
+//! Unlike the TCP-backed transports, a single QUIC connection can carry many concurrent RPCs
+//! without head-of-line blocking between them: every outgoing request opens its own bidirectional
+//! stream, and the server dispatches each inbound stream independently. On the wire, each stream
+//! still carries exactly one request and its response, framed and (de)serialized by the same
+//! generic [`codec`](crate::transport::codec) layer every other transport uses, so QUIC works with
+//! whichever wire [`Format`](crate::transport::codec::Format) feature is enabled.
+
+use super::codec::{self, Format};
+use futures::{compat::Future01CompatExt, prelude::*};
+use quinn::{Connection, Endpoint, Incoming, NewConnection, RecvStream, SendStream};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io;
+
+/// Accepts incoming QUIC connections on `incoming`, yielding a flattened stream of per-stream
+/// [`Transport`](crate::transport::Transport)s -- one per RPC -- ready to hand to
+/// `Server::incoming`.
+pub fn incoming<F, Item, SinkItem>(
+    incoming: Incoming,
+) -> impl Stream<Item = io::Result<impl crate::transport::Transport<Item = Item, SinkItem = SinkItem>>>
+where
+    F: Format,
+    Item: DeserializeOwned,
+    SinkItem: Serialize,
+{
+    incoming
+        .compat()
+        .map_ok(|connecting| connecting.compat())
+        .try_filter_map(|connecting| async move {
+            let NewConnection {
+                connection, bi_streams, ..
+            } = await!(connecting).map_err(codec::io_err)?;
+            Ok(Some(bi_streams.compat().try_filter_map(move |(send, recv)| {
+                future::ready(Ok(Some(stream_transport::<F, _, _>(connection.clone(), send, recv))))
+            })))
+        })
+        .try_flatten()
+}
+
+/// Opens a new bidirectional QUIC stream on `connection` and returns a
+/// [`Transport`](crate::transport::Transport) over it -- one stream per RPC, so unrelated calls
+/// never block each other.
+pub async fn connect<F, Item, SinkItem>(
+    endpoint: &Endpoint,
+    connection: Connection,
+) -> io::Result<impl crate::transport::Transport<Item = Item, SinkItem = SinkItem>>
+where
+    F: Format,
+    Item: DeserializeOwned,
+    SinkItem: Serialize,
+{
+    let _ = endpoint;
+    let (send, recv) = await!(connection.open_bi().compat()).map_err(codec::io_err)?;
+    Ok(stream_transport::<F, _, _>(connection, send, recv))
+}
+
+fn stream_transport<F, Item, SinkItem>(
+    connection: Connection,
+    send: SendStream,
+    recv: RecvStream,
+) -> impl crate::transport::Transport<Item = Item, SinkItem = SinkItem>
+where
+    F: Format,
+    Item: DeserializeOwned,
+    SinkItem: Serialize,
+{
+    let local_addr = connection.local_addr();
+    let peer_addr = connection.remote_address();
+    codec::new::<F, _, _>(ReadWriteStream { send, recv }, local_addr, peer_addr)
+}
+
+/// Adapts a QUIC `(SendStream, RecvStream)` pair to a single `AsyncRead + AsyncWrite`, reusing
+/// the existing length-delimited [`codec`](crate::transport::codec) framing rather than
+/// reinventing it per-protocol.
+struct ReadWriteStream {
+    send: SendStream,
+    recv: RecvStream,
+}
+
+// quinn's SendStream/RecvStream are driven by polling -- a read or write can legitimately
+// return NotReady because of flow control, not because anything's wrong -- so `Read`/`Write`
+// below just translate that into the `WouldBlock` tokio_io's `AsyncRead`/`AsyncWrite` default
+// poll_* methods already know how to turn back into `NotReady`; they don't do any real blocking.
+impl io::Read for ReadWriteStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.recv.poll_read(buf) {
+            Ok(futures_legacy::Async::Ready(n)) => Ok(n),
+            Ok(futures_legacy::Async::NotReady) => Err(io::ErrorKind::WouldBlock.into()),
+            Err(e) => Err(codec::io_err(e)),
+        }
+    }
+}
+
+impl io::Write for ReadWriteStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.send.poll_write(buf) {
+            Ok(futures_legacy::Async::Ready(n)) => Ok(n),
+            Ok(futures_legacy::Async::NotReady) => Err(io::ErrorKind::WouldBlock.into()),
+            Err(e) => Err(codec::io_err(e)),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl tokio_io::AsyncRead for ReadWriteStream {}
+
+impl tokio_io::AsyncWrite for ReadWriteStream {
+    fn shutdown(&mut self) -> std::result::Result<futures_legacy::Async<()>, io::Error> {
+        self.send.finish().map(futures_legacy::Async::Ready)
+    }
+}
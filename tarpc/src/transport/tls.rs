@@ -0,0 +1,71 @@
+// Copyright 2018 Google LLC
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! A TLS-secured transport, built on `tokio-rustls`.
+//!
+//! This module wraps the accept/connect handshake futures of `tokio-rustls` so that, once the
+//! handshake completes, the resulting `TlsStream` is handed straight to the existing
+//! length-delimited [`codec`](crate::transport::codec) framing layer, generic over the same wire
+//! [`Format`](crate::transport::codec::Format) every other transport plugs in -- TLS doesn't care
+//! which codec feature is enabled. Callers don't see the handshake at all: the server side gets a
+//! stream of already-secured [`Transport`](crate::transport::Transport)s, and the client side gets
+//! back a single one.
+
+use super::codec::{self, Format};
+use futures::{compat::Future01CompatExt, prelude::*};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{io, net::SocketAddr, sync::Arc};
+use tokio_rustls::{
+    rustls::{ClientConfig, ServerConfig},
+    TlsAcceptor, TlsConnector,
+};
+use webpki::DNSNameRef;
+
+/// Wraps a stream of accepted `TcpStream`s in the async TLS accept handshake, yielding a stream
+/// of [`Transport`](crate::transport::Transport)s ready to hand to `Server::incoming`.
+pub fn incoming<F, Item, SinkItem>(
+    incoming: impl Stream<Item = io::Result<tokio_tcp::TcpStream>>,
+    config: Arc<ServerConfig>,
+) -> impl Stream<Item = io::Result<impl crate::transport::Transport<Item = Item, SinkItem = SinkItem>>>
+where
+    F: Format,
+    Item: DeserializeOwned,
+    SinkItem: Serialize,
+{
+    let acceptor = TlsAcceptor::from(config);
+    incoming.and_then(move |tcp_stream| {
+        let acceptor = acceptor.clone();
+        async move {
+            let local_addr = tcp_stream.local_addr()?;
+            let peer_addr = tcp_stream.peer_addr()?;
+            let tls_stream = await!(acceptor.accept(tcp_stream).compat())?;
+            Ok(codec::new::<F, _, _>(tls_stream, local_addr, peer_addr))
+        }
+    })
+}
+
+/// Connects to `server_addr`, completes the TLS handshake using `domain` for SNI, and returns a
+/// [`Transport`](crate::transport::Transport) over the resulting encrypted stream.
+pub async fn connect<F, Item, SinkItem>(
+    server_addr: SocketAddr,
+    domain: &str,
+    config: Arc<ClientConfig>,
+) -> io::Result<impl crate::transport::Transport<Item = Item, SinkItem = SinkItem>>
+where
+    F: Format,
+    Item: DeserializeOwned,
+    SinkItem: Serialize,
+{
+    let tcp_stream = await!(tokio_tcp::TcpStream::connect(&server_addr).compat())?;
+    let local_addr = tcp_stream.local_addr()?;
+    let peer_addr = tcp_stream.peer_addr()?;
+
+    let connector = TlsConnector::from(config);
+    let domain = DNSNameRef::try_from_ascii_str(domain).map_err(codec::io_err)?;
+    let tls_stream = await!(connector.connect(domain, tcp_stream).compat())?;
+
+    Ok(codec::new::<F, _, _>(tls_stream, local_addr, peer_addr))
+}
@@ -0,0 +1,146 @@
+// Copyright 2018 Google LLC
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! A WebSocket transport, built on `tungstenite`.
+//!
+//! This lets tarpc be reached from environments where only HTTP(S) ports are open, such as
+//! browsers. Each binary WebSocket message carries one `Item`/`SinkItem` (de)serialized with
+//! wire [`Format`](crate::transport::codec::Format) `F`, the same format `transport::codec::new`
+//! would put on the wire, just without its own length prefix -- WebSocket framing already
+//! delimits messages for us.
+
+use super::codec::{self, Format};
+use futures::{compat::Future01CompatExt, prelude::*};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio_tungstenite::{accept_async, connect_async, tungstenite::Message, WebSocketStream};
+
+/// Performs the WebSocket upgrade handshake on an accepted `TcpStream` and returns a
+/// [`Transport`](crate::transport::Transport) over it, ready to hand to `Server::incoming`.
+pub async fn accept<F, Item, SinkItem>(
+    io: tokio_tcp::TcpStream,
+) -> io::Result<impl crate::transport::Transport<Item = Item, SinkItem = SinkItem>>
+where
+    F: Format,
+    Item: DeserializeOwned,
+    SinkItem: Serialize,
+{
+    let local_addr = io.local_addr()?;
+    let peer_addr = io.peer_addr()?;
+    let ws_stream = await!(accept_async(io).compat()).map_err(codec::io_err)?;
+    Ok(crate::transport::new(
+        WsTransport::<_, F, _, _>::new(ws_stream),
+        local_addr,
+        peer_addr,
+    ))
+}
+
+/// Dials a `ws://`/`wss://` `url` and returns a [`Transport`](crate::transport::Transport) over
+/// the resulting WebSocket connection.
+pub async fn connect<F, Item, SinkItem>(
+    url: &str,
+) -> io::Result<impl crate::transport::Transport<Item = Item, SinkItem = SinkItem>>
+where
+    F: Format,
+    Item: DeserializeOwned,
+    SinkItem: Serialize,
+{
+    let url = url::Url::parse(url).map_err(codec::io_err)?;
+    let host = url.host_str().ok_or_else(|| codec::io_err("url has no host"))?;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| codec::io_err("url has no port"))?;
+    // Resolve on tokio's reactor rather than the blocking std resolver, so a slow DNS lookup
+    // doesn't stall the thread driving every other connection.
+    let peer_addr = await!(tokio::net::lookup_host((host, port)).compat())?
+        .next()
+        .ok_or_else(|| codec::io_err("could not resolve host"))?;
+    let (ws_stream, _response) = await!(connect_async(url.clone()).compat()).map_err(codec::io_err)?;
+    // A client socket's local address isn't meaningful the way a server's is; tarpc only uses it
+    // for logging, so the unspecified address is an honest placeholder.
+    let local_addr = "0.0.0.0:0".parse().unwrap();
+    Ok(crate::transport::new(
+        WsTransport::<_, F, _, _>::new(ws_stream),
+        local_addr,
+        peer_addr,
+    ))
+}
+
+/// Adapts a `WebSocketStream` -- which speaks whole `Message`s -- to the `Stream`/`Sink` of
+/// already-(de)serialized `Item`/`SinkItem` values that `transport::new` expects, using `F` to
+/// (de)serialize each message's payload.
+struct WsTransport<S, F, Item, SinkItem> {
+    inner: WebSocketStream<S>,
+    _format: std::marker::PhantomData<(F, Item, SinkItem)>,
+}
+
+impl<S, F, Item, SinkItem> WsTransport<S, F, Item, SinkItem> {
+    fn new(inner: WebSocketStream<S>) -> Self {
+        WsTransport {
+            inner,
+            _format: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<S, F, Item, SinkItem> Stream for WsTransport<S, F, Item, SinkItem>
+where
+    S: Unpin,
+    WebSocketStream<S>: Stream<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    F: Format,
+    Item: DeserializeOwned,
+{
+    type Item = io::Result<Item>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            return match Pin::new(&mut self.inner).poll_next(cx) {
+                Poll::Ready(Some(Ok(Message::Binary(bytes)))) => {
+                    Poll::Ready(Some(F::decode(&bytes)))
+                }
+                // Text frames are reserved for non-Rust peers speaking e.g. `transport::jsonrpc`;
+                // skip anything else rather than failing the whole connection.
+                Poll::Ready(Some(Ok(_))) => continue,
+                Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(codec::io_err(e)))),
+                Poll::Ready(None) => Poll::Ready(None),
+                Poll::Pending => Poll::Pending,
+            };
+        }
+    }
+}
+
+impl<S, F, Item, SinkItem> Sink<SinkItem> for WsTransport<S, F, Item, SinkItem>
+where
+    S: Unpin,
+    WebSocketStream<S>: Sink<Message, SinkError = tokio_tungstenite::tungstenite::Error> + Unpin,
+    F: Format,
+    SinkItem: Serialize,
+{
+    type SinkError = io::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::SinkError>> {
+        Pin::new(&mut self.inner).poll_ready(cx).map_err(codec::io_err)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: SinkItem) -> Result<(), Self::SinkError> {
+        let bytes = F::encode(&item)?;
+        Pin::new(&mut self.inner)
+            .start_send(Message::Binary(bytes.to_vec()))
+            .map_err(codec::io_err)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::SinkError>> {
+        Pin::new(&mut self.inner).poll_flush(cx).map_err(codec::io_err)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::SinkError>> {
+        Pin::new(&mut self.inner).poll_close(cx).map_err(codec::io_err)
+    }
+}